@@ -14,26 +14,47 @@ use tower::{Service, ServiceBuilder};
 
 use tendermint::{
     abci::{
-        response::{self, PrepareProposal},
+        response::{self, apply_snapshot_chunk, PrepareProposal},
         Event, EventAttributeIndexExt,
     },
+    block::Height,
     v0_38::abci::request,
 };
 
 use tower_abci::{
+    snapshot::{ChunkReassembler, Snapshotter},
     v038::{split, Server},
     BoxError,
 };
 
-use tendermint::abci::types::ExecTxResult;
+use tendermint::abci::types::{ExecTxResult, Snapshot};
 use tendermint::v0_38::abci::{Request, Response};
 
+/// The only snapshot format this application produces.
+const SNAPSHOT_FORMAT: u32 = 1;
+
+/// How many recent snapshots to keep available for state-sync.
+const SNAPSHOTS_TO_KEEP: usize = 2;
+
 /// In-memory, hashmap-backed key-value store ABCI application.
 #[derive(Clone, Debug, Default)]
 pub struct KVStore {
     store: HashMap<String, String>,
     height: u32,
     app_hash: [u8; 8],
+    /// The most recent snapshots, chunked and ready to serve to state-syncing
+    /// peers.
+    snapshots: Vec<StoredSnapshot>,
+    /// An in-progress restore, if this node is state-syncing.
+    restore: Option<(u32, ChunkReassembler)>,
+}
+
+/// A snapshot of the store at a committed height, pre-chunked for serving.
+#[derive(Clone, Debug)]
+struct StoredSnapshot {
+    height: u32,
+    hash: [u8; 32],
+    chunks: Vec<Bytes>,
 }
 
 impl Service<Request> for KVStore {
@@ -66,15 +87,21 @@ impl Service<Request> for KVStore {
             Request::FinalizeBlock(block) => Response::FinalizeBlock(self.finalize_block(block)),
             Request::Commit => Response::Commit(self.commit()),
 
+            // state-sync messages
+            Request::ListSnapshots => Response::ListSnapshots(self.list_snapshots()),
+            Request::OfferSnapshot(offer) => Response::OfferSnapshot(self.offer_snapshot(offer)),
+            Request::LoadSnapshotChunk(load) => {
+                Response::LoadSnapshotChunk(self.load_snapshot_chunk(load))
+            }
+            Request::ApplySnapshotChunk(apply) => {
+                Response::ApplySnapshotChunk(self.apply_snapshot_chunk(apply))
+            }
+
             // unhandled messages
             Request::Flush => Response::Flush,
             Request::Echo(_) => Response::Echo(Default::default()),
             Request::InitChain(_) => Response::InitChain(Default::default()),
             Request::CheckTx(_) => Response::CheckTx(Default::default()),
-            Request::ListSnapshots => Response::ListSnapshots(Default::default()),
-            Request::OfferSnapshot(_) => Response::OfferSnapshot(Default::default()),
-            Request::LoadSnapshotChunk(_) => Response::LoadSnapshotChunk(Default::default()),
-            Request::ApplySnapshotChunk(_) => Response::ApplySnapshotChunk(Default::default()),
         };
         tracing::info!(?rsp);
         async move { Ok(rsp) }.boxed()
@@ -155,6 +182,8 @@ impl KVStore {
         // As in the other kvstore examples, just use store.len() as the "hash"
         self.app_hash = self.compute_apphash();
         self.height += 1;
+        // Advance, then snapshot the state of the block we just committed.
+        self.take_snapshot();
 
         response::Commit {
             // This field is ignored for CometBFT >= 0.38
@@ -163,6 +192,119 @@ impl KVStore {
         }
     }
 
+    /// Serializes and chunks the current store, retaining it as a snapshot for
+    /// the height just committed.
+    fn take_snapshot(&mut self) {
+        let snapshotter = Snapshotter::default();
+        let data = serialize_store(&self.store);
+        let hash = Snapshotter::hash(&data);
+        let chunks = snapshotter.chunk(&data);
+
+        self.snapshots.push(StoredSnapshot {
+            height: self.height,
+            hash,
+            chunks,
+        });
+        if self.snapshots.len() > SNAPSHOTS_TO_KEEP {
+            let excess = self.snapshots.len() - SNAPSHOTS_TO_KEEP;
+            self.snapshots.drain(..excess);
+        }
+    }
+
+    fn list_snapshots(&self) -> response::ListSnapshots {
+        let snapshots = self
+            .snapshots
+            .iter()
+            .map(|snapshot| Snapshot {
+                height: Height::from(snapshot.height),
+                format: SNAPSHOT_FORMAT,
+                chunks: snapshot.chunks.len() as u32,
+                hash: snapshot.hash.to_vec().into(),
+                metadata: Bytes::new(),
+            })
+            .collect();
+
+        response::ListSnapshots { snapshots }
+    }
+
+    fn load_snapshot_chunk(
+        &self,
+        load: request::LoadSnapshotChunk,
+    ) -> response::LoadSnapshotChunk {
+        let height = load.height.value();
+        let chunk = self
+            .snapshots
+            .iter()
+            .find(|snapshot| {
+                load.format == SNAPSHOT_FORMAT && u64::from(snapshot.height) == height
+            })
+            .and_then(|snapshot| snapshot.chunks.get(load.chunk as usize).cloned())
+            .unwrap_or_default();
+
+        response::LoadSnapshotChunk { chunk }
+    }
+
+    fn offer_snapshot(&mut self, offer: request::OfferSnapshot) -> response::OfferSnapshot {
+        // Only this application's own format can be restored.
+        if offer.snapshot.format != SNAPSHOT_FORMAT {
+            return response::OfferSnapshot::RejectFormat;
+        }
+
+        let hash: [u8; 32] = match offer.snapshot.hash.as_ref().try_into() {
+            Ok(hash) => hash,
+            // Not a SHA-256 digest: refuse so CometBFT falls back to block sync.
+            Err(_) => return response::OfferSnapshot::Reject,
+        };
+
+        let height = offer.snapshot.height.value() as u32;
+        self.restore = Some((height, ChunkReassembler::new(offer.snapshot.chunks, hash)));
+        response::OfferSnapshot::Accept
+    }
+
+    fn apply_snapshot_chunk(
+        &mut self,
+        apply: request::ApplySnapshotChunk,
+    ) -> response::ApplySnapshotChunk {
+        let accept = response::ApplySnapshotChunk {
+            result: apply_snapshot_chunk::Result::Accept,
+            refetch_chunks: vec![],
+            reject_senders: vec![],
+        };
+        let reject = response::ApplySnapshotChunk {
+            result: apply_snapshot_chunk::Result::RejectSnapshot,
+            refetch_chunks: vec![],
+            reject_senders: vec![],
+        };
+
+        let Some((_, reassembler)) = self.restore.as_mut() else {
+            // No snapshot was offered; nothing to apply.
+            return reject;
+        };
+
+        // An out-of-range index means the offered snapshot was malformed.
+        if reassembler.add(apply.index, apply.chunk).is_err() {
+            self.restore = None;
+            return reject;
+        }
+
+        if !reassembler.is_complete() {
+            return accept;
+        }
+
+        // All chunks are in; verify the hash and rebuild the store.
+        let (height, reassembler) = self.restore.take().expect("restore in progress");
+        match reassembler.finish().ok().and_then(|data| deserialize_store(&data)) {
+            Some(store) => {
+                self.store = store;
+                self.height = height;
+                self.app_hash = self.compute_apphash();
+                accept
+            }
+            // Hash mismatch or corrupt payload: reject so block sync takes over.
+            None => reject,
+        }
+    }
+
     fn extend_vote(&self, _vote: request::ExtendVote) -> response::ExtendVote {
         response::ExtendVote {
             vote_extension: Bytes::default(),
@@ -178,6 +320,60 @@ impl KVStore {
     }
 }
 
+/// Serializes the store into a deterministic, length-prefixed byte string:
+/// an entry count followed by `(len, key, len, value)` records in sorted key
+/// order, so every node snapshots identical bytes for identical state.
+fn serialize_store(store: &HashMap<String, String>) -> Vec<u8> {
+    let mut entries: Vec<(&String, &String)> = store.iter().collect();
+    entries.sort();
+
+    let mut out = Vec::new();
+    out.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+    for (key, value) in entries {
+        out.extend_from_slice(&(key.len() as u32).to_be_bytes());
+        out.extend_from_slice(key.as_bytes());
+        out.extend_from_slice(&(value.len() as u32).to_be_bytes());
+        out.extend_from_slice(value.as_bytes());
+    }
+    out
+}
+
+/// Inverse of [`serialize_store`]. Returns `None` if `data` is truncated or not
+/// valid UTF-8.
+fn deserialize_store(data: &[u8]) -> Option<HashMap<String, String>> {
+    fn take<'a>(data: &mut &'a [u8], n: usize) -> Option<&'a [u8]> {
+        if data.len() < n {
+            return None;
+        }
+        let (head, tail) = data.split_at(n);
+        *data = tail;
+        Some(head)
+    }
+
+    fn take_u32(data: &mut &[u8]) -> Option<usize> {
+        let bytes = take(data, 4)?;
+        Some(u32::from_be_bytes(bytes.try_into().ok()?) as usize)
+    }
+
+    fn take_string(data: &mut &[u8]) -> Option<String> {
+        let len = take_u32(data)?;
+        let bytes = take(data, len)?;
+        String::from_utf8(bytes.to_vec()).ok()
+    }
+
+    let mut cursor = data;
+    let count = take_u32(&mut cursor)?;
+    let mut store = HashMap::with_capacity(count);
+    for _ in 0..count {
+        let key = take_string(&mut cursor)?;
+        let value = take_string(&mut cursor)?;
+        store.insert(key, value);
+    }
+
+    // Reject trailing garbage.
+    cursor.is_empty().then_some(store)
+}
+
 #[derive(Debug, StructOpt)]
 struct Opt {
     /// Bind the TCP server to this host.
@@ -0,0 +1,60 @@
+//! Error types for the buffer worker, forked from `tower::buffer::error`.
+
+use std::{fmt, sync::Arc};
+
+use crate::BoxError;
+
+/// An error produced by a [`Service`](tower::Service) wrapped by a buffer
+/// worker while it was being made ready, returned to the caller whose request
+/// the worker was serving.
+#[derive(Clone)]
+pub(crate) struct ServiceError {
+    inner: Arc<BoxError>,
+}
+
+impl ServiceError {
+    pub(crate) fn new(inner: BoxError) -> ServiceError {
+        ServiceError {
+            inner: Arc::new(inner),
+        }
+    }
+}
+
+impl fmt::Debug for ServiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.inner, f)
+    }
+}
+
+impl fmt::Display for ServiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "buffered service failed: {}", self.inner)
+    }
+}
+
+impl std::error::Error for ServiceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&**self.inner)
+    }
+}
+
+/// An error produced when the buffer's worker has closed and is no longer
+/// accepting requests.
+#[derive(Debug)]
+pub(crate) struct Closed {
+    _p: (),
+}
+
+impl Closed {
+    pub(crate) fn new() -> Self {
+        Closed { _p: () }
+    }
+}
+
+impl fmt::Display for Closed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("buffer worker closed")
+    }
+}
+
+impl std::error::Error for Closed {}
@@ -0,0 +1,78 @@
+//! The future returned to a buffer caller, forked from
+//! `tower::buffer::future`.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::ready;
+use pin_project::pin_project;
+use tokio::sync::oneshot;
+
+use super::error::{Closed, ServiceError};
+use super::message::ResponseFuture as WorkerFuture;
+use crate::BoxError;
+
+/// Future returned by a buffer handle. It first resolves the worker's
+/// acknowledgement (the boxed response future, or the readiness error the
+/// worker hit), then drives that response future to completion.
+#[pin_project]
+pub(crate) struct ResponseFuture<T> {
+    #[pin]
+    state: ResponseState<T>,
+}
+
+#[pin_project(project = StateProj)]
+enum ResponseState<T> {
+    Failed(Option<BoxError>),
+    Rx(#[pin] oneshot::Receiver<Result<WorkerFuture<T>, ServiceError>>),
+    Poll(#[pin] WorkerFuture<T>),
+}
+
+impl<T> ResponseFuture<T> {
+    pub(crate) fn new(rx: oneshot::Receiver<Result<WorkerFuture<T>, ServiceError>>) -> Self {
+        ResponseFuture {
+            state: ResponseState::Rx(rx),
+        }
+    }
+
+    pub(crate) fn failed(err: BoxError) -> Self {
+        ResponseFuture {
+            state: ResponseState::Failed(Some(err)),
+        }
+    }
+
+    /// Wraps a future that performs its own enqueue before resolving the
+    /// response, used when the send must be *awaited* for backpressure rather
+    /// than attempted up front.
+    pub(crate) fn boxed(fut: WorkerFuture<T>) -> Self {
+        ResponseFuture {
+            state: ResponseState::Poll(fut),
+        }
+    }
+}
+
+impl<T> Future for ResponseFuture<T> {
+    type Output = Result<T, BoxError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+
+        loop {
+            let next = match this.state.as_mut().project() {
+                StateProj::Failed(err) => {
+                    return Poll::Ready(Err(err.take().expect("polled after completion")));
+                }
+                StateProj::Rx(rx) => match ready!(rx.poll(cx)) {
+                    Ok(Ok(fut)) => ResponseState::Poll(fut),
+                    Ok(Err(err)) => return Poll::Ready(Err(err.into())),
+                    Err(_) => return Poll::Ready(Err(Closed::new().into())),
+                },
+                StateProj::Poll(fut) => return fut.poll(cx),
+            };
+            this.state.set(next);
+        }
+    }
+}
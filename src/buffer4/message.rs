@@ -0,0 +1,25 @@
+//! The message a buffer handle hands to its worker, forked from
+//! `tower::buffer::message`.
+
+use tokio::sync::oneshot;
+
+use super::error::ServiceError;
+use crate::BoxError;
+
+/// The boxed response future a worker produces once it has `call`ed the inner
+/// service. It is handed back to the caller so the caller — not the worker —
+/// drives the application work, which is what lets distinct categories run
+/// concurrently.
+pub(crate) type ResponseFuture<T> =
+    std::pin::Pin<Box<dyn std::future::Future<Output = Result<T, BoxError>> + Send>>;
+
+/// Channel over which the worker returns the response future (or the error it
+/// observed while making the category service ready).
+pub(crate) type Tx<T> = oneshot::Sender<Result<ResponseFuture<T>, ServiceError>>;
+
+/// Message sent over a single category channel to the shared worker.
+pub(crate) struct Message<Request, T> {
+    pub(crate) request: Request,
+    pub(crate) tx: Tx<T>,
+    pub(crate) span: tracing::Span,
+}
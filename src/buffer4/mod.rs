@@ -0,0 +1,25 @@
+//! A fork of [`tower::buffer`] that multiplexes four independently-buffered
+//! request streams onto a single worker task.
+//!
+//! CometBFT opens one socket connection per ABCI "category" (consensus,
+//! mempool, snapshot, info) but an application's state is shared, so the four
+//! connections must ultimately be serialized through one place. This module
+//! gives each category its own channel into a single [`Worker`], letting the
+//! worker decide — by [`SchedulingPolicy`] — which category to service next
+//! while preserving FIFO order *within* each category.
+
+mod error;
+mod future;
+mod message;
+mod service;
+mod worker;
+
+pub(crate) use self::error::{Closed, ServiceError};
+pub(crate) use self::message::Message;
+pub(crate) use self::service::Buffer;
+pub(crate) use self::worker::Worker;
+pub use self::worker::SchedulingPolicy;
+
+// The request categorization (`Categorize`/`Category`) lives in `transport` so
+// it can be part of the shared, version-agnostic surface; the worker and handle
+// import it from there directly.
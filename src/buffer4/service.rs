@@ -0,0 +1,143 @@
+//! The buffer handle, forked from `tower::buffer::service`.
+
+use std::{
+    marker::PhantomData,
+    task::{Context, Poll},
+};
+
+use tokio::sync::{mpsc, oneshot};
+use tower::Service;
+
+use super::error::Closed;
+use super::future::ResponseFuture;
+use super::message::Message;
+use crate::transport::{Categorize, Category};
+use crate::BoxError;
+
+/// A cloneable handle that accepts a version's combined `Request`, sorts it
+/// into a category with [`Categorize`], and forwards it to the shared
+/// [`Worker`](super::Worker) over that category's channel.
+pub(crate) struct Buffer<Req, Resp>
+where
+    Req: Categorize,
+{
+    consensus: mpsc::Sender<Message<Req::Consensus, Resp>>,
+    mempool: mpsc::Sender<Message<Req::Mempool, Resp>>,
+    snapshot: mpsc::Sender<Message<Req::Snapshot, Resp>>,
+    info: mpsc::Sender<Message<Req::Info, Resp>>,
+    _resp: PhantomData<fn() -> Resp>,
+}
+
+/// The four receiving ends produced alongside a [`Buffer`], consumed by the
+/// worker.
+pub(crate) struct Receivers<Req, Resp>
+where
+    Req: Categorize,
+{
+    pub(crate) consensus: mpsc::Receiver<Message<Req::Consensus, Resp>>,
+    pub(crate) mempool: mpsc::Receiver<Message<Req::Mempool, Resp>>,
+    pub(crate) snapshot: mpsc::Receiver<Message<Req::Snapshot, Resp>>,
+    pub(crate) info: mpsc::Receiver<Message<Req::Info, Resp>>,
+}
+
+impl<Req, Resp> Buffer<Req, Resp>
+where
+    Req: Categorize,
+{
+    /// Builds a handle and the matching receivers, each channel bounded by
+    /// `bound`.
+    pub(crate) fn pair(bound: usize) -> (Self, Receivers<Req, Resp>) {
+        let (consensus_tx, consensus_rx) = mpsc::channel(bound);
+        let (mempool_tx, mempool_rx) = mpsc::channel(bound);
+        let (snapshot_tx, snapshot_rx) = mpsc::channel(bound);
+        let (info_tx, info_rx) = mpsc::channel(bound);
+        (
+            Buffer {
+                consensus: consensus_tx,
+                mempool: mempool_tx,
+                snapshot: snapshot_tx,
+                info: info_tx,
+                _resp: PhantomData,
+            },
+            Receivers {
+                consensus: consensus_rx,
+                mempool: mempool_rx,
+                snapshot: snapshot_rx,
+                info: info_rx,
+            },
+        )
+    }
+
+    fn enqueue<R>(tx: &mpsc::Sender<Message<R, Resp>>, request: R) -> ResponseFuture<Resp>
+    where
+        R: Send + 'static,
+        Resp: Send + 'static,
+    {
+        let (response_tx, response_rx) = oneshot::channel();
+        let message = Message {
+            request,
+            tx: response_tx,
+            span: tracing::Span::current(),
+        };
+        let tx = tx.clone();
+        ResponseFuture::boxed(Box::pin(async move {
+            // Awaiting the send applies real backpressure: a momentarily full
+            // category queue parks this request until the worker drains a slot,
+            // preserving per-queue FIFO rather than shedding it. Only a dropped
+            // worker — the channel actually closing — is terminal.
+            if tx.send(message).await.is_err() {
+                return Err(Closed::new().into());
+            }
+            ResponseFuture::new(response_rx).await
+        }))
+    }
+}
+
+impl<Req, Resp> Service<Req> for Buffer<Req, Resp>
+where
+    Req: Categorize,
+    Req::Consensus: Send + 'static,
+    Req::Mempool: Send + 'static,
+    Req::Snapshot: Send + 'static,
+    Req::Info: Send + 'static,
+    Resp: Send + 'static,
+{
+    type Response = Resp;
+    type Error = BoxError;
+    type Future = ResponseFuture<Resp>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // Each category is independently bounded; backpressure is applied by
+        // the per-call future awaiting a queue permit, so readiness here is
+        // unconditional and a full queue parks the caller instead of erroring.
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        match req.categorize() {
+            Ok(Category::Consensus(r)) => Self::enqueue(&self.consensus, r),
+            Ok(Category::Mempool(r)) => Self::enqueue(&self.mempool, r),
+            Ok(Category::Snapshot(r)) => Self::enqueue(&self.snapshot, r),
+            Ok(Category::Info(r)) => Self::enqueue(&self.info, r),
+            Err(_) => ResponseFuture::failed(
+                "request was not categorizable and should have been handled by the transport"
+                    .into(),
+            ),
+        }
+    }
+}
+
+impl<Req, Resp> Clone for Buffer<Req, Resp>
+where
+    Req: Categorize,
+{
+    fn clone(&self) -> Self {
+        Buffer {
+            consensus: self.consensus.clone(),
+            mempool: self.mempool.clone(),
+            snapshot: self.snapshot.clone(),
+            info: self.info.clone(),
+            _resp: PhantomData,
+        }
+    }
+}
@@ -0,0 +1,181 @@
+//! The shared worker task, forked from `tower::buffer::worker`.
+
+use tower::{Service, ServiceExt};
+
+use super::error::ServiceError;
+use super::message::{Message, ResponseFuture};
+use tokio::sync::mpsc::error::TryRecvError;
+
+use super::service::Receivers;
+use crate::transport::Categorize;
+use crate::BoxError;
+
+/// How the worker chooses which category queue to serve next.
+///
+/// FIFO order *within* a single queue is preserved under every policy, so the
+/// consensus connection's requests are never reordered relative to CometBFT's
+/// stream.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SchedulingPolicy {
+    /// Always serve the highest-priority non-empty queue (consensus, then
+    /// mempool, then snapshot, then info). A steady stream of consensus work
+    /// can starve the lower queues.
+    StrictPriority,
+    /// Serve up to `weight` ready items from each queue in turn before
+    /// advancing to the next, skipping empty queues. This bounds how long a
+    /// busy queue can hold off the others.
+    ///
+    /// Every weight must be `>= 1`: a weight of `0` would let a steady stream
+    /// on the higher-priority queues starve that category indefinitely, the
+    /// very thing this policy exists to prevent. `ServerBuilder::scheduling`
+    /// rejects a zero weight.
+    WeightedRoundRobin {
+        consensus: u32,
+        mempool: u32,
+        snapshot: u32,
+        info: u32,
+    },
+}
+
+impl Default for SchedulingPolicy {
+    fn default() -> Self {
+        SchedulingPolicy::StrictPriority
+    }
+}
+
+/// Drives the four category services from a single task, pulling work off the
+/// four channels according to a [`SchedulingPolicy`].
+pub(crate) struct Worker<Req, Resp, C, M, S, I>
+where
+    Req: Categorize,
+{
+    pub(crate) rx: Receivers<Req, Resp>,
+    pub(crate) consensus: C,
+    pub(crate) mempool: M,
+    pub(crate) snapshot: S,
+    pub(crate) info: I,
+    pub(crate) scheduling: SchedulingPolicy,
+}
+
+impl<Req, Resp, C, M, S, I> Worker<Req, Resp, C, M, S, I>
+where
+    Req: Categorize,
+    Resp: Send + 'static,
+    C: Service<Req::Consensus>,
+    C::Response: Into<Resp>,
+    C::Error: Into<BoxError>,
+    C::Future: Send + 'static,
+    M: Service<Req::Mempool>,
+    M::Response: Into<Resp>,
+    M::Error: Into<BoxError>,
+    M::Future: Send + 'static,
+    S: Service<Req::Snapshot>,
+    S::Response: Into<Resp>,
+    S::Error: Into<BoxError>,
+    S::Future: Send + 'static,
+    I: Service<Req::Info>,
+    I::Response: Into<Resp>,
+    I::Error: Into<BoxError>,
+    I::Future: Send + 'static,
+{
+    pub(crate) async fn run(self) {
+        let Worker {
+            mut rx,
+            mut consensus,
+            mut mempool,
+            mut snapshot,
+            mut info,
+            scheduling,
+        } = self;
+
+        match scheduling {
+            SchedulingPolicy::StrictPriority => loop {
+                // `biased` polls the branches top-to-bottom, so a ready
+                // consensus message is always preferred over lower queues.
+                tokio::select! {
+                    biased;
+                    Some(msg) = rx.consensus.recv() => serve(&mut consensus, msg).await,
+                    Some(msg) = rx.mempool.recv() => serve(&mut mempool, msg).await,
+                    Some(msg) = rx.snapshot.recv() => serve(&mut snapshot, msg).await,
+                    Some(msg) = rx.info.recv() => serve(&mut info, msg).await,
+                    else => break,
+                }
+            },
+
+            SchedulingPolicy::WeightedRoundRobin {
+                consensus: wc,
+                mempool: wm,
+                snapshot: ws,
+                info: wi,
+            } => {
+                // Serve up to `weight` already-ready items from each queue in
+                // turn. `try_recv` preserves per-queue FIFO order, and a round
+                // that serves nothing blocks until some queue becomes ready so
+                // the worker never busy-spins.
+                macro_rules! drain {
+                    ($rx:expr, $svc:expr, $weight:expr) => {{
+                        let mut served = 0u32;
+                        while served < $weight {
+                            match $rx.try_recv() {
+                                Ok(msg) => {
+                                    serve(&mut $svc, msg).await;
+                                    served += 1;
+                                }
+                                Err(TryRecvError::Empty)
+                                | Err(TryRecvError::Disconnected) => break,
+                            }
+                        }
+                        served
+                    }};
+                }
+
+                loop {
+                    let mut served = 0;
+                    served += drain!(rx.consensus, consensus, wc);
+                    served += drain!(rx.mempool, mempool, wm);
+                    served += drain!(rx.snapshot, snapshot, ws);
+                    served += drain!(rx.info, info, wi);
+
+                    if served == 0 {
+                        // Every queue was empty; wait for the next message (or
+                        // exit once all handles are dropped).
+                        tokio::select! {
+                            biased;
+                            Some(msg) = rx.consensus.recv() => serve(&mut consensus, msg).await,
+                            Some(msg) = rx.mempool.recv() => serve(&mut mempool, msg).await,
+                            Some(msg) = rx.snapshot.recv() => serve(&mut snapshot, msg).await,
+                            Some(msg) = rx.info.recv() => serve(&mut info, msg).await,
+                            else => break,
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Makes `svc` ready, `call`s it, and hands the (boxed) response future back to
+/// the caller so the application work runs off the worker's critical path.
+async fn serve<Svc, R, Resp>(svc: &mut Svc, msg: Message<R, Resp>)
+where
+    Svc: Service<R>,
+    Svc::Response: Into<Resp>,
+    Svc::Error: Into<BoxError>,
+    Svc::Future: Send + 'static,
+    Resp: 'static,
+{
+    let Message { request, tx, span } = msg;
+    let _enter = span.enter();
+
+    match svc.ready().await {
+        Ok(svc) => {
+            let fut = svc.call(request);
+            let boxed: ResponseFuture<Resp> =
+                Box::pin(async move { fut.await.map(Into::into).map_err(Into::into) });
+            let _ = tx.send(Ok(boxed));
+        }
+        Err(e) => {
+            let _ = tx.send(Err(ServiceError::new(e.into())));
+        }
+    }
+}
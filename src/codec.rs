@@ -0,0 +1,146 @@
+//! Shared length-delimited protobuf framing used by every protocol version.
+//!
+//! Each message is a protobuf payload prefixed by its length as a base-128
+//! varint (CometBFT's "delimited" format). Only the concrete protobuf/domain
+//! types differ between versions, so the framing itself lives here and the
+//! per-version codecs ([`v037::codec`](crate::v037), [`v038::codec`](crate::v038))
+//! are thin wrappers that pick the types.
+
+use bytes::{Buf, BytesMut};
+use prost::Message;
+
+use crate::BoxError;
+
+/// The largest frame the decoder will accept. The length delimiter is read
+/// straight off the wire and is attacker-controlled, so an oversized varint
+/// would otherwise drive `BytesMut::reserve` into a "capacity overflow" panic
+/// (or an OOM abort) before a single payload byte arrives. We reject anything
+/// past this ceiling instead — it comfortably exceeds any real ABCI message.
+const MAX_FRAME_LEN: usize = 256 * 1024 * 1024;
+
+/// Decodes one delimited frame of protobuf type `P` into the domain type `T`,
+/// or returns `Ok(None)` when `src` does not yet hold a complete frame.
+pub(crate) fn decode_delimited<P, T>(src: &mut BytesMut) -> Result<Option<T>, BoxError>
+where
+    P: Message + Default,
+    T: TryFrom<P>,
+    T::Error: Into<BoxError>,
+{
+    // Peek the length prefix off a borrowed slice so a partial varint/payload
+    // leaves `src` untouched — and, crucially, uncopied: `BytesMut::clone`
+    // deep-copies, which would be O(n²) as a frame trickles in over reads.
+    let mut cursor = &src[..];
+    let encoded_len = match prost::encoding::decode_varint(&mut cursor) {
+        Ok(len) => len as usize,
+        // Not enough bytes to read the length prefix yet.
+        Err(_) => return Ok(None),
+    };
+
+    if encoded_len > MAX_FRAME_LEN {
+        return Err(format!(
+            "ABCI frame length {encoded_len} exceeds the maximum of {MAX_FRAME_LEN} bytes"
+        )
+        .into());
+    }
+
+    if cursor.len() < encoded_len {
+        // Hint the buffer about how much more we need, then wait.
+        src.reserve(encoded_len - cursor.len());
+        return Ok(None);
+    }
+
+    // Drop the varint prefix, then split off exactly the payload.
+    let delimiter_len = src.len() - cursor.len();
+    src.advance(delimiter_len);
+    let payload = src.split_to(encoded_len);
+
+    let proto = P::decode(payload)?;
+    let message = T::try_from(proto).map_err(Into::into)?;
+    Ok(Some(message))
+}
+
+/// Encodes `message` as protobuf type `P` and writes the delimited frame.
+pub(crate) fn encode_delimited<P, T>(message: T, dst: &mut BytesMut) -> Result<(), BoxError>
+where
+    T: Into<P>,
+    P: Message,
+{
+    let proto: P = message.into();
+    let encoded_len = proto.encoded_len();
+    dst.reserve(encoded_len + prost::length_delimiter_len(encoded_len));
+    prost::encoding::encode_varint(encoded_len as u64, dst);
+    proto.encode(dst)?;
+    Ok(())
+}
+
+/// Defines a version's `ServerCodec`/`ClientCodec` by binding the framing above
+/// to that version's protobuf module and domain types. The two halves are
+/// identical across versions apart from those types, so each version expands
+/// this one definition instead of copying the `Decoder`/`Encoder` impls.
+macro_rules! socket_codecs {
+    ($pb:path, $Request:ty, $Response:ty) => {
+        /// Reads `Request` frames and writes `Response` frames: the server half
+        /// of the socket protocol.
+        #[derive(Default)]
+        pub(crate) struct ServerCodec;
+
+        impl ::tokio_util::codec::Decoder for ServerCodec {
+            type Item = $Request;
+            type Error = $crate::BoxError;
+
+            fn decode(
+                &mut self,
+                src: &mut ::bytes::BytesMut,
+            ) -> ::std::result::Result<::std::option::Option<$Request>, $crate::BoxError> {
+                use $pb as pb;
+                $crate::codec::decode_delimited::<pb::Request, $Request>(src)
+            }
+        }
+
+        impl ::tokio_util::codec::Encoder<$Response> for ServerCodec {
+            type Error = $crate::BoxError;
+
+            fn encode(
+                &mut self,
+                item: $Response,
+                dst: &mut ::bytes::BytesMut,
+            ) -> ::std::result::Result<(), $crate::BoxError> {
+                use $pb as pb;
+                $crate::codec::encode_delimited::<pb::Response, $Response>(item, dst)
+            }
+        }
+
+        /// Writes `Request` frames and reads `Response` frames: the client half
+        /// of the socket protocol.
+        #[derive(Default)]
+        pub(crate) struct ClientCodec;
+
+        impl ::tokio_util::codec::Decoder for ClientCodec {
+            type Item = $Response;
+            type Error = $crate::BoxError;
+
+            fn decode(
+                &mut self,
+                src: &mut ::bytes::BytesMut,
+            ) -> ::std::result::Result<::std::option::Option<$Response>, $crate::BoxError> {
+                use $pb as pb;
+                $crate::codec::decode_delimited::<pb::Response, $Response>(src)
+            }
+        }
+
+        impl ::tokio_util::codec::Encoder<$Request> for ClientCodec {
+            type Error = $crate::BoxError;
+
+            fn encode(
+                &mut self,
+                item: $Request,
+                dst: &mut ::bytes::BytesMut,
+            ) -> ::std::result::Result<(), $crate::BoxError> {
+                use $pb as pb;
+                $crate::codec::encode_delimited::<pb::Request, $Request>(item, dst)
+            }
+        }
+    };
+}
+
+pub(crate) use socket_codecs;
@@ -1,24 +1,29 @@
 #![cfg_attr(feature = "doc", feature(extended_key_value_attributes))]
 #![cfg_attr(feature = "doc", doc = include_str!("../README.md"))]
 
-/// ABCI requests.
-pub mod request;
-#[doc(inline)]
-pub use request::{ConsensusRequest, InfoRequest, MempoolRequest, Request, SnapshotRequest};
-
-/// ABCI responses.
-pub mod response;
-#[doc(inline)]
-pub use response::{ConsensusResponse, InfoResponse, MempoolResponse, Response, SnapshotResponse};
-
 /// A fork of tower::buffer @ `e1760d38` that has four queues feeding
 /// the same worker task, with different priorities.
 mod buffer4;
+pub use buffer4::SchedulingPolicy;
+
+/// Shared, length-delimited protobuf framing used by every protocol version.
+mod codec;
 
-mod server;
-pub use server::Server;
+/// Version-agnostic server machinery shared across protocol versions.
+pub mod transport;
 
-pub mod split;
+/// ABCI 0.37 (CometBFT 0.37) support.
+pub mod v037;
+
+/// ABCI 0.38 (CometBFT 0.38) support.
+pub mod v038;
+
+/// Helpers for wiring ABCI state-sync snapshots into a snapshot-category
+/// `Service`.
+pub mod snapshot;
 
 /// A convenient error type alias.
 pub type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+#[cfg(test)]
+mod tests;
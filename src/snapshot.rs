@@ -0,0 +1,152 @@
+//! Reusable chunking and reassembly helpers for ABCI state-sync snapshots.
+//!
+//! CometBFT fetches a snapshot as a sequence of independently-addressed chunks
+//! and only trusts the result once it matches the snapshot `hash` advertised by
+//! `ListSnapshots`. An application is free to choose its own serialization, but
+//! the mechanics — fixed-size chunking, chunk indexing, hashing the full
+//! snapshot, and verifying a restore — are the same everywhere. This module
+//! factors that out so a snapshot-category `Service` only has to decide *what*
+//! to serialize.
+
+use bytes::Bytes;
+use sha2::{Digest, Sha256};
+
+/// Default chunk size (64 KiB). CometBFT streams chunks independently, so the
+/// exact value only trades off per-chunk overhead against memory.
+pub const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Splits a serialized snapshot into fixed-size chunks and computes the hash
+/// CometBFT verifies the restore against.
+#[derive(Clone, Copy, Debug)]
+pub struct Snapshotter {
+    chunk_size: usize,
+}
+
+impl Default for Snapshotter {
+    fn default() -> Self {
+        Snapshotter {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+        }
+    }
+}
+
+impl Snapshotter {
+    /// Creates a snapshotter with the given chunk size.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub fn new(chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "snapshot chunk size must be non-zero");
+        Snapshotter { chunk_size }
+    }
+
+    /// The SHA-256 hash of the full snapshot, advertised by `ListSnapshots` and
+    /// verified when a restore completes.
+    pub fn hash(data: &[u8]) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(data);
+        hasher.finalize().into()
+    }
+
+    /// Splits `data` into chunks of at most the configured size. An empty
+    /// snapshot still yields a single (empty) chunk, so the chunk count is
+    /// always at least one.
+    pub fn chunk(&self, data: &[u8]) -> Vec<Bytes> {
+        if data.is_empty() {
+            return vec![Bytes::new()];
+        }
+        data.chunks(self.chunk_size)
+            .map(Bytes::copy_from_slice)
+            .collect()
+    }
+}
+
+/// Accumulates snapshot chunks (which may arrive out of order) and verifies the
+/// reassembled bytes against the snapshot hash.
+#[derive(Clone, Debug)]
+pub struct ChunkReassembler {
+    hash: [u8; 32],
+    chunks: Vec<Option<Bytes>>,
+}
+
+/// Why a chunk could not be accepted, so a caller can pick the matching
+/// `ApplySnapshotChunk` result and fall back to block sync.
+#[derive(Clone, Debug)]
+pub enum ChunkError {
+    /// The chunk index was outside the range advertised for this snapshot.
+    IndexOutOfRange { index: u32, expected: u32 },
+    /// Every chunk arrived, but the reassembled bytes did not match the hash.
+    HashMismatch,
+}
+
+impl std::fmt::Display for ChunkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChunkError::IndexOutOfRange { index, expected } => write!(
+                f,
+                "snapshot chunk index {index} out of range (expected < {expected})"
+            ),
+            ChunkError::HashMismatch => f.write_str("reassembled snapshot did not match its hash"),
+        }
+    }
+}
+
+impl std::error::Error for ChunkError {}
+
+impl ChunkReassembler {
+    /// Prepares to reassemble a snapshot of `num_chunks` chunks, committing to
+    /// the expected `hash`.
+    pub fn new(num_chunks: u32, hash: [u8; 32]) -> Self {
+        ChunkReassembler {
+            hash,
+            chunks: vec![None; num_chunks as usize],
+        }
+    }
+
+    /// The number of chunks this snapshot was declared to have.
+    pub fn num_chunks(&self) -> u32 {
+        self.chunks.len() as u32
+    }
+
+    /// Records a chunk, rejecting an out-of-range index.
+    pub fn add(&mut self, index: u32, chunk: Bytes) -> Result<(), ChunkError> {
+        let expected = self.num_chunks();
+        let slot = self
+            .chunks
+            .get_mut(index as usize)
+            .ok_or(ChunkError::IndexOutOfRange { index, expected })?;
+        *slot = Some(chunk);
+        Ok(())
+    }
+
+    /// The index of the lowest chunk not yet received, or `None` when complete.
+    pub fn next_missing(&self) -> Option<u32> {
+        self.chunks
+            .iter()
+            .position(Option::is_none)
+            .map(|i| i as u32)
+    }
+
+    /// Whether every chunk has been received.
+    pub fn is_complete(&self) -> bool {
+        self.chunks.iter().all(Option::is_some)
+    }
+
+    /// Concatenates the chunks and verifies them against the snapshot hash.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called before [`is_complete`](Self::is_complete) returns true.
+    pub fn finish(self) -> Result<Vec<u8>, ChunkError> {
+        let mut data = Vec::new();
+        for chunk in self.chunks {
+            data.extend_from_slice(&chunk.expect("finish called before reassembly completed"));
+        }
+        if Snapshotter::hash(&data) == self.hash {
+            Ok(data)
+        } else {
+            Err(ChunkError::HashMismatch)
+        }
+    }
+}
@@ -0,0 +1,490 @@
+//! Version-agnostic ABCI server machinery.
+//!
+//! The socket transport, the builder, and the [`buffer4`](crate::buffer4)
+//! worker are identical across ABCI protocol versions; only the request and
+//! response types — and how a combined request is sorted into a category —
+//! differ. That difference is captured by the [`Categorize`] and [`Protocol`]
+//! traits, so each version (e.g. [`v038`](crate::v038)) is a thin layer that
+//! supplies types and a codec and re-exports a specialized [`Server`].
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::{
+    future::{BoxFuture, FutureExt},
+    sink::SinkExt,
+    stream::{FuturesOrdered, StreamExt},
+};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs, UnixListener, UnixStream};
+use tokio::sync::{mpsc, oneshot};
+use tokio_util::codec::{Decoder, Encoder, FramedRead, FramedWrite};
+use tower::Service;
+
+use crate::buffer4::{Buffer, SchedulingPolicy, Worker};
+use crate::BoxError;
+
+/// How many requests may be queued per category before the worker applies
+/// backpressure.
+const DEFAULT_BUFFER_BOUND: usize = 10;
+
+/// Which category a request was sorted into by [`Categorize`].
+///
+/// The variant order doubles as the strict-priority order: `Consensus`
+/// outranks `Mempool`, which outranks `Snapshot`, which outranks `Info`.
+pub enum Category<C, M, S, I> {
+    Consensus(C),
+    Mempool(M),
+    Snapshot(S),
+    Info(I),
+}
+
+/// Sorts a protocol version's combined `Request` into one of the four ABCI
+/// categories. Implemented per version so the worker and transport can be
+/// shared while only the categorization differs. `Flush`, which the transport
+/// answers itself, returns `Err(self)`.
+pub trait Categorize: Sized {
+    type Consensus;
+    type Mempool;
+    type Snapshot;
+    type Info;
+
+    fn categorize(
+        self,
+    ) -> Result<Category<Self::Consensus, Self::Mempool, Self::Snapshot, Self::Info>, Self>;
+}
+
+/// Everything that distinguishes one ABCI protocol version from another: its
+/// request/response types, their wire codec, and how `Flush` is answered.
+pub trait Protocol: 'static {
+    /// The combined request type, sortable into categories.
+    type Request: Categorize + Send + 'static;
+    /// The combined response type.
+    type Response: Send + 'static;
+    /// A codec that reads `Request` frames and writes `Response` frames: the
+    /// server half of the socket protocol.
+    type Codec: Decoder<Item = Self::Request, Error = BoxError>
+        + Encoder<Self::Response, Error = BoxError>
+        + Default
+        + Send
+        + 'static;
+    /// The mirror-image codec used by [`Client`]: writes `Request` frames and
+    /// reads `Response` frames.
+    type ClientCodec: Decoder<Item = Self::Response, Error = BoxError>
+        + Encoder<Self::Request, Error = BoxError>
+        + Default
+        + Send
+        + 'static;
+
+    /// Whether `request` is the connection-level `Flush` barrier.
+    fn is_flush(request: &Self::Request) -> bool;
+    /// The response to a `Flush`.
+    fn flush_response() -> Self::Response;
+}
+
+/// An ABCI server for protocol version `P`, constructed via
+/// [`Server::builder`].
+///
+/// The four category services are driven by a single background worker; each
+/// accepted connection gets a clone of the worker handle and is framed with
+/// `P`'s codec.
+pub struct Server<P: Protocol> {
+    buffer: Buffer<P::Request, P::Response>,
+}
+
+impl<P: Protocol> Server<P> {
+    /// Begins building a server. All four category services must be supplied
+    /// before calling [`ServerBuilder::finish`].
+    pub fn builder() -> ServerBuilder<P, (), (), (), ()> {
+        ServerBuilder::new()
+    }
+
+    /// Serves the ABCI protocol over TCP, accepting connections forever.
+    pub async fn listen_tcp<A: ToSocketAddrs>(self, addr: A) -> Result<(), BoxError> {
+        let listener = TcpListener::bind(addr).await?;
+        let local = listener.local_addr()?;
+        tracing::info!(?local, "serving ABCI over tcp");
+        loop {
+            let (socket, peer) = listener.accept().await?;
+            socket.set_nodelay(true)?;
+            let buffer = self.buffer.clone();
+            tokio::spawn(async move {
+                if let Err(e) = serve_connection::<P, _>(buffer, socket).await {
+                    tracing::error!(?peer, error = %e, "connection terminated");
+                }
+            });
+        }
+    }
+
+    /// Serves the ABCI protocol over a Unix domain socket, accepting
+    /// connections forever.
+    pub async fn listen_unix<Path: AsRef<std::path::Path>>(
+        self,
+        path: Path,
+    ) -> Result<(), BoxError> {
+        let listener = UnixListener::bind(path)?;
+        tracing::info!("serving ABCI over unix socket");
+        loop {
+            let (socket, _peer) = listener.accept().await?;
+            let buffer = self.buffer.clone();
+            tokio::spawn(async move {
+                if let Err(e) = serve_connection::<P, _>(buffer, socket).await {
+                    tracing::error!(error = %e, "connection terminated");
+                }
+            });
+        }
+    }
+
+    /// Exposes the worker handle so version-specific transports (e.g. gRPC) can
+    /// route requests through the same middleware stack.
+    pub(crate) fn buffer(&self) -> Buffer<P::Request, P::Response> {
+        self.buffer.clone()
+    }
+}
+
+/// Drives one socket connection: decode requests, dispatch them through the
+/// worker, and write responses back in submission order.
+pub(crate) async fn serve_connection<P, IO>(
+    mut buffer: Buffer<P::Request, P::Response>,
+    io: IO,
+) -> Result<(), BoxError>
+where
+    P: Protocol,
+    IO: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let (read, write) = tokio::io::split(io);
+    let mut requests = FramedRead::new(read, P::Codec::default());
+    let mut responses = FramedWrite::new(write, P::Codec::default());
+
+    // `FuturesOrdered` yields in submission order even though individual
+    // requests may complete out of order, which is exactly the ordering the
+    // socket protocol requires.
+    let mut inflight = FuturesOrdered::new();
+
+    loop {
+        tokio::select! {
+            biased;
+            // Flush completed responses before accepting more work.
+            Some(rsp) = inflight.next(), if !inflight.is_empty() => {
+                responses.send(rsp?).await?;
+            }
+            req = requests.next() => match req {
+                Some(Ok(request)) => inflight.push_back(dispatch::<P>(&mut buffer, request)),
+                Some(Err(e)) => return Err(e),
+                None => break,
+            },
+        }
+    }
+
+    // The peer closed the read half; finish answering what we accepted.
+    while let Some(rsp) = inflight.next().await {
+        responses.send(rsp?).await?;
+    }
+    Ok(())
+}
+
+/// Produces the response future for a single request. `Flush` is a
+/// connection-level barrier: it resolves immediately, but because it is pushed
+/// onto the ordered queue after prior requests, it is only *written* once those
+/// requests have been answered.
+fn dispatch<P>(
+    buffer: &mut Buffer<P::Request, P::Response>,
+    request: P::Request,
+) -> BoxFuture<'static, Result<P::Response, BoxError>>
+where
+    P: Protocol,
+{
+    if P::is_flush(&request) {
+        futures::future::ready(Ok(P::flush_response())).boxed()
+    } else {
+        buffer.call(request).boxed()
+    }
+}
+
+/// How many requests may be in flight to the connection task before `call`
+/// applies backpressure.
+const CLIENT_BUFFER_BOUND: usize = 16;
+
+/// A response channel paired with the request that produced it.
+type Pending<P> = (
+    <P as Protocol>::Request,
+    oneshot::Sender<Result<<P as Protocol>::Response, BoxError>>,
+);
+
+/// An ABCI socket client for protocol version `P`, the mirror image of
+/// [`Server`].
+///
+/// It speaks the same length-delimited protobuf protocol, but from the other
+/// side: it writes `Request` frames and reads `Response` frames. A single
+/// background task owns the connection and multiplexes outstanding requests
+/// over it, so the client is cheap to clone and call concurrently. This is
+/// useful for writing integration tests against a running app, building ABCI
+/// proxies/middleware, and fuzzing a `Server` without a full CometBFT node.
+pub struct Client<P: Protocol> {
+    tx: mpsc::Sender<Pending<P>>,
+}
+
+impl<P: Protocol> Clone for Client<P> {
+    fn clone(&self) -> Self {
+        Client {
+            tx: self.tx.clone(),
+        }
+    }
+}
+
+impl<P: Protocol> Client<P> {
+    /// Connects to an ABCI server over TCP.
+    pub async fn connect_tcp<A: ToSocketAddrs>(addr: A) -> Result<Client<P>, BoxError> {
+        let stream = TcpStream::connect(addr).await?;
+        stream.set_nodelay(true)?;
+        Ok(Client::from_io(stream))
+    }
+
+    /// Connects to an ABCI server over a Unix domain socket.
+    pub async fn connect_unix<Path: AsRef<std::path::Path>>(
+        path: Path,
+    ) -> Result<Client<P>, BoxError> {
+        let stream = UnixStream::connect(path).await?;
+        Ok(Client::from_io(stream))
+    }
+
+    /// Spawns the connection task around an already-established transport.
+    pub(crate) fn from_io<IO>(io: IO) -> Client<P>
+    where
+        IO: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel(CLIENT_BUFFER_BOUND);
+        tokio::spawn(connection::<P, _>(io, rx));
+        Client { tx }
+    }
+}
+
+/// The background task: write each request, remember its response channel, and
+/// match responses to requests in FIFO order (the socket protocol answers a
+/// connection's requests in submission order). Matching in order is also what
+/// preserves `Flush` semantics — a `Flush` response is only read after every
+/// prior request on the connection has been answered.
+async fn connection<P, IO>(io: IO, mut rx: mpsc::Receiver<Pending<P>>)
+where
+    P: Protocol,
+    IO: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let (read, write) = tokio::io::split(io);
+    let mut responses = FramedRead::new(read, P::ClientCodec::default());
+    let mut requests = FramedWrite::new(write, P::ClientCodec::default());
+    let mut pending: VecDeque<oneshot::Sender<Result<P::Response, BoxError>>> = VecDeque::new();
+
+    loop {
+        tokio::select! {
+            biased;
+            response = responses.next() => match response {
+                Some(Ok(rsp)) => {
+                    if let Some(tx) = pending.pop_front() {
+                        let _ = tx.send(Ok(rsp));
+                    } else {
+                        tracing::warn!("received an ABCI response with no request pending");
+                    }
+                }
+                Some(Err(e)) => {
+                    if let Some(tx) = pending.pop_front() {
+                        let _ = tx.send(Err(e));
+                    }
+                    break;
+                }
+                None => break,
+            },
+            message = rx.recv() => match message {
+                Some((request, tx)) => match requests.send(request).await {
+                    Ok(()) => pending.push_back(tx),
+                    Err(e) => {
+                        let _ = tx.send(Err(e));
+                        break;
+                    }
+                },
+                // Every `Client` handle has been dropped.
+                None => break,
+            },
+        }
+    }
+
+    // The connection is gone; fail anyone still waiting.
+    for tx in pending {
+        let _ = tx.send(Err("ABCI client connection closed".into()));
+    }
+}
+
+impl<P: Protocol> Service<P::Request> for Client<P> {
+    type Response = P::Response;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<P::Response, BoxError>> + Send + 'static>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: P::Request) -> Self::Future {
+        let tx = self.tx.clone();
+        Box::pin(async move {
+            let (response_tx, response_rx) = oneshot::channel();
+            tx.send((req, response_tx))
+                .await
+                .map_err(|_| BoxError::from("ABCI client connection closed"))?;
+            response_rx
+                .await
+                .map_err(|_| BoxError::from("ABCI client connection closed"))?
+        })
+    }
+}
+
+/// Builder for [`Server`]. Each setter records one of the four category
+/// services; the type parameters track which have been supplied.
+pub struct ServerBuilder<P, C, M, I, S> {
+    consensus: Option<C>,
+    mempool: Option<M>,
+    info: Option<I>,
+    snapshot: Option<S>,
+    scheduling: SchedulingPolicy,
+    _protocol: std::marker::PhantomData<fn() -> P>,
+}
+
+impl<P: Protocol> ServerBuilder<P, (), (), (), ()> {
+    fn new() -> Self {
+        ServerBuilder {
+            consensus: None,
+            mempool: None,
+            info: None,
+            snapshot: None,
+            scheduling: SchedulingPolicy::default(),
+            _protocol: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<P, C, M, I, S> ServerBuilder<P, C, M, I, S> {
+    /// Sets the consensus-connection service.
+    pub fn consensus<C2>(self, consensus: C2) -> ServerBuilder<P, C2, M, I, S> {
+        ServerBuilder {
+            consensus: Some(consensus),
+            mempool: self.mempool,
+            info: self.info,
+            snapshot: self.snapshot,
+            scheduling: self.scheduling,
+            _protocol: std::marker::PhantomData,
+        }
+    }
+
+    /// Sets the mempool-connection service.
+    pub fn mempool<M2>(self, mempool: M2) -> ServerBuilder<P, C, M2, I, S> {
+        ServerBuilder {
+            consensus: self.consensus,
+            mempool: Some(mempool),
+            info: self.info,
+            snapshot: self.snapshot,
+            scheduling: self.scheduling,
+            _protocol: std::marker::PhantomData,
+        }
+    }
+
+    /// Sets the info-connection service.
+    pub fn info<I2>(self, info: I2) -> ServerBuilder<P, C, M, I2, S> {
+        ServerBuilder {
+            consensus: self.consensus,
+            mempool: self.mempool,
+            info: Some(info),
+            snapshot: self.snapshot,
+            scheduling: self.scheduling,
+            _protocol: std::marker::PhantomData,
+        }
+    }
+
+    /// Sets the snapshot-connection service.
+    pub fn snapshot<S2>(self, snapshot: S2) -> ServerBuilder<P, C, M, I, S2> {
+        ServerBuilder {
+            consensus: self.consensus,
+            mempool: self.mempool,
+            info: self.info,
+            snapshot: Some(snapshot),
+            scheduling: self.scheduling,
+            _protocol: std::marker::PhantomData,
+        }
+    }
+
+    /// Sets the worker's scheduling policy. Defaults to
+    /// [`SchedulingPolicy::StrictPriority`].
+    ///
+    /// Weighted round-robin bounds how long a busy queue can hold off the
+    /// others, e.g. to keep a steady stream of consensus work from starving
+    /// info and mempool:
+    ///
+    /// ```ignore
+    /// .scheduling(SchedulingPolicy::WeightedRoundRobin {
+    ///     consensus: 8,
+    ///     mempool: 2,
+    ///     snapshot: 1,
+    ///     info: 1,
+    /// })
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if a [`SchedulingPolicy::WeightedRoundRobin`] weight is `0`; a
+    /// zero weight would permanently starve its queue, so it is a configuration
+    /// error rather than a run-time condition.
+    pub fn scheduling(mut self, scheduling: SchedulingPolicy) -> Self {
+        if let SchedulingPolicy::WeightedRoundRobin {
+            consensus,
+            mempool,
+            snapshot,
+            info,
+        } = scheduling
+        {
+            assert!(
+                consensus >= 1 && mempool >= 1 && snapshot >= 1 && info >= 1,
+                "WeightedRoundRobin weights must all be >= 1; a weight of 0 starves its queue",
+            );
+        }
+        self.scheduling = scheduling;
+        self
+    }
+}
+
+impl<P, C, M, I, S> ServerBuilder<P, C, M, I, S>
+where
+    P: Protocol,
+    C: Service<<P::Request as Categorize>::Consensus, Error = BoxError> + Send + 'static,
+    C::Response: Into<P::Response>,
+    C::Future: Send + 'static,
+    M: Service<<P::Request as Categorize>::Mempool, Error = BoxError> + Send + 'static,
+    M::Response: Into<P::Response>,
+    M::Future: Send + 'static,
+    I: Service<<P::Request as Categorize>::Info, Error = BoxError> + Send + 'static,
+    I::Response: Into<P::Response>,
+    I::Future: Send + 'static,
+    S: Service<<P::Request as Categorize>::Snapshot, Error = BoxError> + Send + 'static,
+    S::Response: Into<P::Response>,
+    S::Future: Send + 'static,
+{
+    /// Spawns the shared worker and returns the assembled [`Server`], or `None`
+    /// if any of the four services was not supplied.
+    pub fn finish(self) -> Option<Server<P>> {
+        let consensus = self.consensus?;
+        let mempool = self.mempool?;
+        let info = self.info?;
+        let snapshot = self.snapshot?;
+
+        let (buffer, receivers) = Buffer::pair(DEFAULT_BUFFER_BOUND);
+        let worker = Worker {
+            rx: receivers,
+            consensus,
+            mempool,
+            snapshot,
+            info,
+            scheduling: self.scheduling,
+        };
+        tokio::spawn(worker.run());
+
+        Some(Server { buffer })
+    }
+}
@@ -0,0 +1,60 @@
+//! ABCI support for CometBFT 0.37.
+//!
+//! The 0.37 consensus flow is the legacy `BeginBlock`/`DeliverTx`/`EndBlock`/
+//! `Commit` lifecycle (plus `PrepareProposal`/`ProcessProposal`), without the
+//! `ExtendVote`/`VerifyVoteExtension` and `FinalizeBlock` messages introduced
+//! in 0.38. The worker and transport are shared with 0.38 via
+//! [`crate::transport`]; only the request/response categorization differs.
+
+mod codec;
+mod server;
+pub mod split;
+
+pub use server::{Client, Server, ServerBuilder, V037};
+
+#[doc(inline)]
+pub use tendermint::v0_37::abci::{
+    ConsensusRequest, InfoRequest, MempoolRequest, Request, SnapshotRequest,
+};
+#[doc(inline)]
+pub use tendermint::v0_37::abci::{
+    ConsensusResponse, InfoResponse, MempoolResponse, Response, SnapshotResponse,
+};
+
+use crate::transport::{Categorize, Category};
+
+impl Categorize for Request {
+    type Consensus = ConsensusRequest;
+    type Mempool = MempoolRequest;
+    type Snapshot = SnapshotRequest;
+    type Info = InfoRequest;
+
+    fn categorize(
+        self,
+    ) -> Result<Category<ConsensusRequest, MempoolRequest, SnapshotRequest, InfoRequest>, Self> {
+        use Category::*;
+        Ok(match self {
+            Request::InitChain(x) => Consensus(ConsensusRequest::InitChain(x)),
+            Request::PrepareProposal(x) => Consensus(ConsensusRequest::PrepareProposal(x)),
+            Request::ProcessProposal(x) => Consensus(ConsensusRequest::ProcessProposal(x)),
+            Request::BeginBlock(x) => Consensus(ConsensusRequest::BeginBlock(x)),
+            Request::DeliverTx(x) => Consensus(ConsensusRequest::DeliverTx(x)),
+            Request::EndBlock(x) => Consensus(ConsensusRequest::EndBlock(x)),
+            Request::Commit => Consensus(ConsensusRequest::Commit),
+
+            Request::CheckTx(x) => Mempool(MempoolRequest::CheckTx(x)),
+
+            Request::ListSnapshots => Snapshot(SnapshotRequest::ListSnapshots),
+            Request::OfferSnapshot(x) => Snapshot(SnapshotRequest::OfferSnapshot(x)),
+            Request::LoadSnapshotChunk(x) => Snapshot(SnapshotRequest::LoadSnapshotChunk(x)),
+            Request::ApplySnapshotChunk(x) => Snapshot(SnapshotRequest::ApplySnapshotChunk(x)),
+
+            Request::Echo(x) => Info(InfoRequest::Echo(x)),
+            Request::Info(x) => Info(InfoRequest::Info(x)),
+            Request::Query(x) => Info(InfoRequest::Query(x)),
+
+            // `Flush` is a connection-level barrier answered by the transport.
+            Request::Flush => return Err(Request::Flush),
+        })
+    }
+}
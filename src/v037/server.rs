@@ -0,0 +1,36 @@
+//! The ABCI 0.37 server: a thin specialization of the shared
+//! [`transport`](crate::transport) over the 0.37 request/response types.
+
+use crate::transport::{self, Protocol};
+
+use super::codec::{ClientCodec, ServerCodec};
+use super::{Request, Response};
+
+/// Marker type selecting the ABCI 0.37 protocol.
+pub enum V037 {}
+
+impl Protocol for V037 {
+    type Request = Request;
+    type Response = Response;
+    type Codec = ServerCodec;
+    type ClientCodec = ClientCodec;
+
+    fn is_flush(request: &Request) -> bool {
+        matches!(request, Request::Flush)
+    }
+
+    fn flush_response() -> Response {
+        Response::Flush
+    }
+}
+
+/// An ABCI 0.37 server. See [`transport::Server`] for the builder and transport
+/// methods.
+pub type Server = transport::Server<V037>;
+
+/// The builder for an ABCI 0.37 [`Server`].
+pub type ServerBuilder<C, M, I, S> = transport::ServerBuilder<V037, C, M, I, S>;
+
+/// An ABCI 0.37 socket client. See [`transport::Client`] for the design; clones
+/// share the same connection.
+pub type Client = transport::Client<V037>;
@@ -0,0 +1,6 @@
+//! The ABCI 0.38 wire codec: binds the shared delimited framing in
+//! [`crate::codec`] to the 0.38 protobuf/domain types.
+
+use super::{Request, Response};
+
+crate::codec::socket_codecs!(tendermint_proto::v0_38::abci, Request, Response);
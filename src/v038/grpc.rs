@@ -0,0 +1,138 @@
+//! A gRPC transport for the ABCI server.
+//!
+//! CometBFT 0.38 can talk ABCI over gRPC (`--abci grpc`) instead of the
+//! hand-rolled socket codec, which gives operators HTTP/2 multiplexing. This
+//! module adapts the tonic-generated ABCI service onto the same
+//! [`buffer4`](crate::buffer4) worker the socket server uses, so the
+//! load-shed/buffer/rate-limit stack wired up on [`Server::builder`] is applied
+//! identically regardless of transport.
+//!
+//! Enabled with the `grpc` feature.
+
+use tendermint::v0_38::abci::Request;
+use tendermint_proto::v0_38::abci::{self as pb, abci_server::Abci};
+use tower::Service;
+
+use crate::buffer4::Buffer;
+
+/// Implements the tonic ABCI service by forwarding every RPC through the shared
+/// worker handle.
+pub(crate) struct AbciAdapter {
+    buffer: Buffer<Request, tendermint::v0_38::abci::Response>,
+}
+
+impl AbciAdapter {
+    pub(crate) fn new(buffer: Buffer<Request, tendermint::v0_38::abci::Response>) -> Self {
+        AbciAdapter { buffer }
+    }
+
+    /// Converts a protobuf request oneof into the domain `Request`, routes it
+    /// through the worker, and returns the response oneof.
+    async fn route(
+        &self,
+        value: pb::request::Value,
+    ) -> Result<pb::response::Value, tonic::Status> {
+        let proto = pb::Request { value: Some(value) };
+        let request =
+            Request::try_from(proto).map_err(|e| tonic::Status::invalid_argument(e.to_string()))?;
+
+        let mut buffer = self.buffer.clone();
+        let response = buffer
+            .call(request)
+            .await
+            .map_err(|e| tonic::Status::internal(e.to_string()))?;
+
+        pb::Response::from(response)
+            .value
+            .ok_or_else(|| tonic::Status::internal("empty ABCI response"))
+    }
+}
+
+/// Generates a unary RPC method that wraps its proto request in the matching
+/// oneof variant, routes it, and unwraps the matching response variant.
+macro_rules! grpc_method {
+    ($method:ident, $request:ident, $response:ident, $variant:ident) => {
+        async fn $method(
+            &self,
+            request: tonic::Request<pb::$request>,
+        ) -> Result<tonic::Response<pb::$response>, tonic::Status> {
+            let value = pb::request::Value::$variant(request.into_inner());
+            match self.route(value).await? {
+                pb::response::Value::$variant(response) => Ok(tonic::Response::new(response)),
+                _ => Err(tonic::Status::internal(concat!(
+                    "expected ",
+                    stringify!($variant),
+                    " response",
+                ))),
+            }
+        }
+    };
+}
+
+#[tonic::async_trait]
+impl Abci for AbciAdapter {
+    // `Flush` is a connection-level barrier with no application handler; answer
+    // it directly rather than routing it through the worker.
+    async fn flush(
+        &self,
+        _request: tonic::Request<pb::RequestFlush>,
+    ) -> Result<tonic::Response<pb::ResponseFlush>, tonic::Status> {
+        Ok(tonic::Response::new(pb::ResponseFlush {}))
+    }
+
+    grpc_method!(echo, RequestEcho, ResponseEcho, Echo);
+    grpc_method!(info, RequestInfo, ResponseInfo, Info);
+    grpc_method!(init_chain, RequestInitChain, ResponseInitChain, InitChain);
+    grpc_method!(query, RequestQuery, ResponseQuery, Query);
+    grpc_method!(check_tx, RequestCheckTx, ResponseCheckTx, CheckTx);
+    grpc_method!(commit, RequestCommit, ResponseCommit, Commit);
+    grpc_method!(
+        list_snapshots,
+        RequestListSnapshots,
+        ResponseListSnapshots,
+        ListSnapshots
+    );
+    grpc_method!(
+        offer_snapshot,
+        RequestOfferSnapshot,
+        ResponseOfferSnapshot,
+        OfferSnapshot
+    );
+    grpc_method!(
+        load_snapshot_chunk,
+        RequestLoadSnapshotChunk,
+        ResponseLoadSnapshotChunk,
+        LoadSnapshotChunk
+    );
+    grpc_method!(
+        apply_snapshot_chunk,
+        RequestApplySnapshotChunk,
+        ResponseApplySnapshotChunk,
+        ApplySnapshotChunk
+    );
+    grpc_method!(
+        prepare_proposal,
+        RequestPrepareProposal,
+        ResponsePrepareProposal,
+        PrepareProposal
+    );
+    grpc_method!(
+        process_proposal,
+        RequestProcessProposal,
+        ResponseProcessProposal,
+        ProcessProposal
+    );
+    grpc_method!(extend_vote, RequestExtendVote, ResponseExtendVote, ExtendVote);
+    grpc_method!(
+        verify_vote_extension,
+        RequestVerifyVoteExtension,
+        ResponseVerifyVoteExtension,
+        VerifyVoteExtension
+    );
+    grpc_method!(
+        finalize_block,
+        RequestFinalizeBlock,
+        ResponseFinalizeBlock,
+        FinalizeBlock
+    );
+}
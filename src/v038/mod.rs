@@ -0,0 +1,63 @@
+//! ABCI support for CometBFT 0.38.
+//!
+//! The 0.38 consensus flow is `PrepareProposal`/`ProcessProposal`/
+//! `ExtendVote`/`VerifyVoteExtension`/`FinalizeBlock`/`Commit`; the request and
+//! response types are re-exported from the [`tendermint`] crate so applications
+//! only depend on one set of ABCI types.
+
+mod codec;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod server;
+pub mod split;
+
+pub use server::{Client, Server, ServerBuilder, V038};
+
+#[doc(inline)]
+pub use tendermint::v0_38::abci::{
+    ConsensusRequest, InfoRequest, MempoolRequest, Request, SnapshotRequest,
+};
+#[doc(inline)]
+pub use tendermint::v0_38::abci::{
+    ConsensusResponse, InfoResponse, MempoolResponse, Response, SnapshotResponse,
+};
+
+use crate::transport::{Categorize, Category};
+
+impl Categorize for Request {
+    type Consensus = ConsensusRequest;
+    type Mempool = MempoolRequest;
+    type Snapshot = SnapshotRequest;
+    type Info = InfoRequest;
+
+    fn categorize(
+        self,
+    ) -> Result<Category<ConsensusRequest, MempoolRequest, SnapshotRequest, InfoRequest>, Self> {
+        use Category::*;
+        Ok(match self {
+            Request::InitChain(x) => Consensus(ConsensusRequest::InitChain(x)),
+            Request::PrepareProposal(x) => Consensus(ConsensusRequest::PrepareProposal(x)),
+            Request::ProcessProposal(x) => Consensus(ConsensusRequest::ProcessProposal(x)),
+            Request::ExtendVote(x) => Consensus(ConsensusRequest::ExtendVote(x)),
+            Request::VerifyVoteExtension(x) => {
+                Consensus(ConsensusRequest::VerifyVoteExtension(x))
+            }
+            Request::FinalizeBlock(x) => Consensus(ConsensusRequest::FinalizeBlock(x)),
+            Request::Commit => Consensus(ConsensusRequest::Commit),
+
+            Request::CheckTx(x) => Mempool(MempoolRequest::CheckTx(x)),
+
+            Request::ListSnapshots => Snapshot(SnapshotRequest::ListSnapshots),
+            Request::OfferSnapshot(x) => Snapshot(SnapshotRequest::OfferSnapshot(x)),
+            Request::LoadSnapshotChunk(x) => Snapshot(SnapshotRequest::LoadSnapshotChunk(x)),
+            Request::ApplySnapshotChunk(x) => Snapshot(SnapshotRequest::ApplySnapshotChunk(x)),
+
+            Request::Echo(x) => Info(InfoRequest::Echo(x)),
+            Request::Info(x) => Info(InfoRequest::Info(x)),
+            Request::Query(x) => Info(InfoRequest::Query(x)),
+
+            // `Flush` is a connection-level barrier answered by the transport.
+            Request::Flush => return Err(Request::Flush),
+        })
+    }
+}
@@ -0,0 +1,59 @@
+//! The ABCI 0.38 server: a thin specialization of the shared
+//! [`transport`](crate::transport) over the 0.38 request/response types.
+
+use crate::transport::{self, Protocol};
+
+use super::codec::{ClientCodec, ServerCodec};
+use super::{Request, Response};
+
+/// Marker type selecting the ABCI 0.38 protocol.
+pub enum V038 {}
+
+impl Protocol for V038 {
+    type Request = Request;
+    type Response = Response;
+    type Codec = ServerCodec;
+    type ClientCodec = ClientCodec;
+
+    fn is_flush(request: &Request) -> bool {
+        matches!(request, Request::Flush)
+    }
+
+    fn flush_response() -> Response {
+        Response::Flush
+    }
+}
+
+/// An ABCI 0.38 server. See [`transport::Server`] for the builder and transport
+/// methods.
+pub type Server = transport::Server<V038>;
+
+/// The builder for an ABCI 0.38 [`Server`].
+pub type ServerBuilder<C, M, I, S> = transport::ServerBuilder<V038, C, M, I, S>;
+
+/// An ABCI 0.38 socket client. See [`transport::Client`] for the design; clones
+/// share the same connection.
+pub type Client = transport::Client<V038>;
+
+#[cfg(feature = "grpc")]
+impl Server {
+    /// Serves the ABCI protocol over gRPC, accepting connections forever.
+    ///
+    /// This exposes the same four category services behind CometBFT 0.38's gRPC
+    /// ABCI interface, for use with `--abci grpc`. Requests are routed through
+    /// the same worker as the socket transport, so the middleware stack
+    /// configured on [`Server::builder`] applies unchanged.
+    ///
+    /// Requires the `grpc` feature.
+    pub async fn serve_grpc(self, addr: std::net::SocketAddr) -> Result<(), crate::BoxError> {
+        use super::grpc::AbciAdapter;
+        use tendermint_proto::v0_38::abci::abci_server::AbciServer;
+
+        tracing::info!(?addr, "serving ABCI over grpc");
+        tonic::transport::Server::builder()
+            .add_service(AbciServer::new(AbciAdapter::new(self.buffer())))
+            .serve(addr)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,96 @@
+//! Split a single `Service<Request>` into the four category services the
+//! [`Server`](super::Server) expects.
+//!
+//! CometBFT drives four ABCI connections (consensus, mempool, snapshot, info)
+//! but most applications are written as one `Service<Request>`. [`service`]
+//! wraps that service in a [`tower::buffer::Buffer`] so it can be shared, then
+//! hands back four category-typed views of it; each view maps its category
+//! request into a [`Request`] and the resulting [`Response`] back into its
+//! category response.
+
+use std::task::{Context, Poll};
+
+use futures::future::{BoxFuture, FutureExt};
+use tower::{buffer::Buffer, Service};
+
+use super::{
+    ConsensusRequest, ConsensusResponse, InfoRequest, InfoResponse, MempoolRequest,
+    MempoolResponse, Request, Response, SnapshotRequest, SnapshotResponse,
+};
+use crate::BoxError;
+
+/// Splits `service` into its four ABCI category services, buffering the shared
+/// inner service with capacity `bound`.
+pub fn service<S>(service: S, bound: usize) -> (Consensus<S>, Mempool<S>, Snapshot<S>, Info<S>)
+where
+    S: Service<Request, Response = Response, Error = BoxError> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    let bound = std::cmp::max(1, bound);
+    let buffer = Buffer::new(service, bound);
+
+    (
+        Consensus {
+            inner: buffer.clone(),
+        },
+        Mempool {
+            inner: buffer.clone(),
+        },
+        Snapshot {
+            inner: buffer.clone(),
+        },
+        Info { inner: buffer },
+    )
+}
+
+macro_rules! split_category {
+    ($(#[$doc:meta])* $name:ident, $request:ty, $response:ty) => {
+        $(#[$doc])*
+        pub struct $name<S> {
+            inner: Buffer<S, Request>,
+        }
+
+        impl<S> Service<$request> for $name<S>
+        where
+            S: Service<Request, Response = Response, Error = BoxError> + Send + 'static,
+            S::Future: Send + 'static,
+        {
+            type Response = $response;
+            type Error = BoxError;
+            type Future = BoxFuture<'static, Result<$response, BoxError>>;
+
+            fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+                self.inner.poll_ready(cx)
+            }
+
+            fn call(&mut self, req: $request) -> Self::Future {
+                let req: Request = req.into();
+                let fut = self.inner.call(req);
+                async move {
+                    let rsp = fut.await?;
+                    let rsp: $response = rsp.try_into()?;
+                    Ok(rsp)
+                }
+                .boxed()
+            }
+        }
+    };
+}
+
+split_category! {
+    /// Handles the consensus connection (`InitChain`, `*Proposal`, `*Vote*`,
+    /// `FinalizeBlock`, `Commit`).
+    Consensus, ConsensusRequest, ConsensusResponse
+}
+split_category! {
+    /// Handles the mempool connection (`CheckTx`).
+    Mempool, MempoolRequest, MempoolResponse
+}
+split_category! {
+    /// Handles the snapshot (state-sync) connection.
+    Snapshot, SnapshotRequest, SnapshotResponse
+}
+split_category! {
+    /// Handles the info connection (`Echo`, `Info`, `Query`).
+    Info, InfoRequest, InfoResponse
+}